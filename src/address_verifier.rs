@@ -1,149 +1,199 @@
-use anyhow::{Context, Result};
-use chrono::prelude::*;
-use fancy_regex::Regex;
-use futures::StreamExt;
-use log::{error, info};
 use std::{
     collections::HashMap,
+    path::{Path, PathBuf},
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
-use tokio::{spawn, sync::RwLock, time::interval};
+
+use anyhow::{Context, Result};
+use log::{error, info};
+use reqwest::header::{HeaderValue, IF_MODIFIED_SINCE, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, spawn, sync::RwLock, time::interval};
 use xml::reader::{EventReader, XmlEvent};
 
+/// An OFAC numeric `FeatureTypeID`, e.g. `"344"` for Bitcoin addresses. Kept as a `String`
+/// because the SDN XML encodes them as plain attribute text, not integers.
+pub type FeatureTypeId = String;
+
+const OFAC_URL: &str = "https://www.treasury.gov/ofac/downloads/sanctions/1.0/sdn_advanced.xml";
+
+/// What we persist to `cache_path` between restarts, so a restart doesn't force a full re-sync of
+/// the multi-megabyte SDN list.
+#[derive(Default, Serialize, Deserialize)]
+struct DiskCache {
+    last_update: i64,
+    sanctioned_addresses: HashMap<String, bool>,
+}
+
 pub struct AddressVerifier {
     sanctioned_addresses: Arc<RwLock<HashMap<String, bool>>>,
     last_update: Arc<RwLock<i64>>,
+    cache_path: PathBuf,
+    feature_type_ids: Vec<FeatureTypeId>,
 }
 
 impl AddressVerifier {
-    const BTC_ID: &'static str = "344";
+    /// OFAC's `FeatureTypeID` for Bitcoin addresses.
+    pub const BITCOIN_FEATURE_TYPE_ID: &'static str = "344";
+
+    /// Creates a verifier that screens the given OFAC `FeatureTypeID`s (e.g. Bitcoin, and
+    /// whichever other chains' identifiers callers care about), loading any previously persisted
+    /// sanctioned set from `cache_path` so a restart doesn't force a full re-sync.
+    pub async fn new(cache_path: PathBuf, feature_type_ids: Vec<FeatureTypeId>) -> Self {
+        let disk_cache = Self::load_from_disk(&cache_path).await.unwrap_or_default();
 
-    pub fn new() -> Self {
         Self {
-            sanctioned_addresses: Arc::new(RwLock::new(HashMap::new())),
-            last_update: Arc::new(RwLock::new(0)),
+            sanctioned_addresses: Arc::new(RwLock::new(disk_cache.sanctioned_addresses)),
+            last_update: Arc::new(RwLock::new(disk_cache.last_update)),
+            cache_path,
+            feature_type_ids,
         }
     }
 
-    fn extract_from_xml(str_value: &str, tag: &str) -> Result<u32> {
-        let re = Regex::new(&format!(r"(?<={}>)\s*(\w+)(?=<\/{})", tag, tag)).unwrap();
-        let value = re.find(&str_value)?.context("no regex result")?.as_str();
-
-        Ok(value.parse()?)
+    async fn load_from_disk(cache_path: &Path) -> Result<DiskCache> {
+        let bytes = fs::read(cache_path)
+            .await
+            .context("no sanctioned-address cache on disk yet")?;
+        Ok(serde_json::from_slice(&bytes)?)
     }
 
-    /// read the first few bytes from the remote XML file and extract the last update date.
-    /// If there is no fresh data we can skip the parsing of XML which is slow.
-    async fn publish_date() -> Result<i64> {
-        let res =
-            reqwest::get("https://www.treasury.gov/ofac/downloads/sanctions/1.0/sdn_advanced.xml")
-                .await?;
-
-        let head = res
-            .bytes_stream()
-            .take(1)
-            .collect::<Vec<reqwest::Result<_>>>()
+    async fn save_to_disk(&self) -> Result<()> {
+        let disk_cache = DiskCache {
+            last_update: *self.last_update.read().await,
+            sanctioned_addresses: self.sanctioned_addresses.read().await.clone(),
+        };
+        let bytes = serde_json::to_vec(&disk_cache)?;
+        fs::write(&self.cache_path, &bytes)
             .await
-            .into_iter()
-            .collect::<reqwest::Result<Vec<_>>>()?;
-
-        let str_value = String::from_utf8(head[0].to_vec())?;
-        let year = Self::extract_from_xml(&str_value, "Year")?;
-        let day = Self::extract_from_xml(&str_value, "Day")?;
-        let month = Self::extract_from_xml(&str_value, "Month")?;
-        let date = Utc
-            .with_ymd_and_hms(year as i32, month, day, 0, 0, 0)
-            .single()
-            .context("date parse error")?
-            .timestamp();
-
-        Ok(date)
+            .context("failed to persist sanctioned-address cache")
     }
 
-    /// Periodically fetces the latest list from https://www.treasury.gov/ofac/downloads/sanctions/1.0/sdn_advanced.xml
-    /// and updates the list
-    pub async fn start(&self) {
-        let sanctioned_addresses = Arc::clone(&self.sanctioned_addresses);
-        let last_update = Arc::clone(&self.last_update);
+    /// Issues a conditional `HEAD` request against the OFAC list with `If-Modified-Since`, so we
+    /// skip downloading the (multi-megabyte) body entirely unless the publish date actually
+    /// advanced. Replaces the old "stream the first XML chunk and regex out Year/Month/Day",
+    /// which broke whenever the server changed how it chunked the response.
+    async fn remote_last_modified(since: i64) -> Result<Option<i64>> {
+        let since_header = HeaderValue::from_str(&httpdate::fmt_http_date(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(since.max(0) as u64),
+        ))?;
+
+        let res = reqwest::Client::new()
+            .head(OFAC_URL)
+            .header(IF_MODIFIED_SINCE, since_header)
+            .send()
+            .await?;
+
+        if res.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
 
-        spawn(async move {
-            let mut interval = interval(Duration::from_secs(600));
+        let last_modified = res
+            .headers()
+            .get(LAST_MODIFIED)
+            .context("OFAC server did not return a Last-Modified header")?
+            .to_str()?;
+        let last_modified = httpdate::parse_http_date(last_modified)?
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs() as i64;
 
-            loop {
-                let Ok(publish_date) = Self::publish_date().await else {
-                    error!("couldn't extract the OFAC document publish date");
-                    continue;
-                };
+        Ok(Some(last_modified))
+    }
 
-                if *last_update.read().await >= publish_date {
-                    info!("OFAC list is up-to-date");
-                    continue;
+    /// Downloads and parses the full SDN list, keeping only identifiers whose `FeatureTypeID` is
+    /// one of `self.feature_type_ids` (Bitcoin by default, but configurable so ETH/other-chain
+    /// sanctioned identifiers can be screened too).
+    async fn sync(&self, new_last_update: i64) -> Result<()> {
+        info!("OFAC list syncing...");
+        let start = Instant::now();
+        let xml = reqwest::get(OFAC_URL).await?.text().await?;
+        let parser: EventReader<&[u8]> = EventReader::new(xml.as_bytes());
+        let mut inside_feature_elem = false;
+        let mut inside_final_elem = false;
+
+        let mut sanctioned_addresses = self.sanctioned_addresses.write().await;
+
+        for e in parser {
+            match e {
+                Ok(XmlEvent::StartElement {
+                    name, attributes, ..
+                }) => {
+                    if name.local_name == "Feature" {
+                        if attributes.iter().any(|a| {
+                            a.name.local_name == "FeatureTypeID"
+                                && self.feature_type_ids.iter().any(|id| id == &a.value)
+                        }) {
+                            inside_feature_elem = true;
+                        }
+                    } else if name.local_name == "VersionDetail" && inside_feature_elem {
+                        inside_final_elem = true;
+                    }
                 }
+                Ok(XmlEvent::Characters(value)) => {
+                    if inside_final_elem {
+                        sanctioned_addresses.insert(value, true);
+                    }
+                }
+                Ok(XmlEvent::EndElement { name, .. }) => {
+                    if name.local_name == "VersionDetail" && inside_feature_elem {
+                        inside_feature_elem = false;
+                        inside_final_elem = false;
+                    }
+                }
+                Err(e) => {
+                    error!("Error parsing xml: {e}");
+                    break;
+                }
+                _ => {}
+            }
+        }
+        drop(sanctioned_addresses);
+
+        *self.last_update.write().await = new_last_update;
+
+        info!("OFAC synced in {:?}", start.elapsed());
+
+        if let Err(error) = self.save_to_disk().await {
+            error!("couldn't persist sanctioned-address cache: {error}");
+        }
+
+        Ok(())
+    }
 
-                let mut sanctioned_addresses = sanctioned_addresses.write().await;
+    /// Periodically checks whether the OFAC list advanced and, if so, re-syncs it.
+    pub async fn start(self: &Arc<Self>) {
+        let this = Arc::clone(self);
+
+        spawn(async move {
+            let mut interval = interval(Duration::from_secs(600));
+
+            loop {
                 interval.tick().await;
 
-                let mut last_update = last_update.write().await;
-                *last_update = publish_date;
-
-                info!("OFAC list syncing...");
-                let start = Instant::now();
-                let Ok(res) = reqwest::get(
-                    "https://www.treasury.gov/ofac/downloads/sanctions/1.0/sdn_advanced.xml",
-                )
-                .await
-                else {
-                    error!("couldn't fetch OFAC list");
-                    continue;
-                };
-                let Ok(xml) = res.text().await else {
-                    error!("couldn't parse OFAC list");
-                    continue;
-                };
-                let parser: EventReader<&[u8]> = EventReader::new(xml.as_bytes());
-                let mut inside_feature_elem = false;
-                let mut inside_final_elem = false;
-
-                for e in parser {
-                    match e {
-                        Ok(XmlEvent::StartElement {
-                            name, attributes, ..
-                        }) => {
-                            if name.local_name == "Feature" {
-                                if attributes.iter().any(|a| {
-                                    a.name.local_name == "FeatureTypeID" && a.value == Self::BTC_ID
-                                }) {
-                                    inside_feature_elem = true;
-                                }
-                            } else if name.local_name == "VersionDetail" && inside_feature_elem {
-                                inside_final_elem = true;
-                            }
-                        }
-                        Ok(XmlEvent::Characters(value)) => {
-                            if inside_final_elem {
-                                sanctioned_addresses.insert(value, true);
-                            }
-                        }
-                        Ok(XmlEvent::EndElement { name, .. }) => {
-                            if name.local_name == "VersionDetail" && inside_feature_elem {
-                                inside_feature_elem = false;
-                                inside_final_elem = false;
-                            }
-                        }
-                        Err(e) => {
-                            error!("Error parsing xml: {e}");
-                            break;
-                        }
-                        _ => {}
+                let since = *this.last_update.read().await;
+                let new_last_update = match Self::remote_last_modified(since).await {
+                    Ok(Some(date)) => date,
+                    Ok(None) => {
+                        info!("OFAC list is up-to-date");
+                        continue;
                     }
-                }
+                    Err(error) => {
+                        error!("couldn't check the OFAC list's publish date: {error}");
+                        continue;
+                    }
+                };
 
-                let duration = start.elapsed();
-                info!("OFAC synced in {:?}", duration);
+                if let Err(error) = this.sync(new_last_update).await {
+                    error!("OFAC list sync failed: {error}");
+                }
             }
         })
         .await
         .unwrap();
     }
-}
\ No newline at end of file
+
+    /// Returns true if `address` appears in the sanctioned set for any screened `FeatureTypeID`.
+    pub async fn is_sanctioned(&self, address: &str) -> bool {
+        self.sanctioned_addresses.read().await.contains_key(address)
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bitcoin::{OutPoint, Transaction, TxOut, Txid};
+
+use crate::json_rpc_stuff::json_rpc_request;
+
+/// Abstracts over how we reach the Bitcoin network, so `create_transaction`/
+/// `sign_transaction_schnorr` callers aren't forced to run and trust a full `bitcoind` wallet.
+/// [`JsonRpcBackend`] wraps the existing Core RPC path; [`ElectrumBackend`] lets a caller operate
+/// as a light client against an Electrum/Esplora server, the way BDK-based wallets do.
+#[async_trait]
+pub trait ChainBackend {
+    /// Fetches the `TxOut` a given outpoint spends, for use in the `prevouts` slice.
+    async fn get_prevout(&self, outpoint: OutPoint) -> Result<TxOut>;
+
+    /// Estimates a fee rate, in sat/vB, from which a caller can work out `fee_bitcoin_sat`.
+    async fn estimate_fee_rate(&self) -> Result<f64>;
+
+    /// Number of confirmations the UTXO at `outpoint` currently has, or `0` if unconfirmed.
+    async fn get_confirmations(&self, outpoint: OutPoint) -> Result<u32>;
+
+    /// Broadcasts a signed transaction and returns its txid.
+    async fn broadcast(&self, tx: &Transaction) -> Result<Txid>;
+}
+
+/// The original backend: a local Bitcoin Core node's wallet RPC, as used by the `mpc_sign_tx`
+/// tests today.
+pub struct JsonRpcBackend {
+    wallet: Option<String>,
+}
+
+impl JsonRpcBackend {
+    pub fn new(wallet: Option<String>) -> Self {
+        Self { wallet }
+    }
+}
+
+#[async_trait]
+impl ChainBackend for JsonRpcBackend {
+    async fn get_prevout(&self, outpoint: OutPoint) -> Result<TxOut> {
+        let response = json_rpc_request(
+            self.wallet.as_deref(),
+            "gettxout",
+            &[
+                serde_json::value::to_raw_value(&outpoint.txid).unwrap(),
+                serde_json::value::to_raw_value(&outpoint.vout).unwrap(),
+            ],
+        )
+        .await?;
+        let response: jsonrpc::Response = serde_json::from_str(&response)?;
+        let result: serde_json::Value = response.result()?;
+        let value_btc = result["value"].as_f64().context("missing value")?;
+        let script_hex = result["scriptPubKey"]["hex"]
+            .as_str()
+            .context("missing scriptPubKey")?;
+
+        Ok(TxOut {
+            value: bitcoin::Amount::from_btc(value_btc)?,
+            script_pubkey: bitcoin::ScriptBuf::from_hex(script_hex)?,
+        })
+    }
+
+    async fn estimate_fee_rate(&self) -> Result<f64> {
+        let response = json_rpc_request(self.wallet.as_deref(), "estimatesmartfee", &[
+            serde_json::value::to_raw_value(&6u32).unwrap(),
+        ])
+        .await?;
+        let response: jsonrpc::Response = serde_json::from_str(&response)?;
+        let result: serde_json::Value = response.result()?;
+        let btc_per_kvb = result["feerate"].as_f64().context("missing feerate")?;
+
+        Ok(btc_per_kvb * 100_000.0) // BTC/kvB -> sat/vB
+    }
+
+    async fn get_confirmations(&self, outpoint: OutPoint) -> Result<u32> {
+        let response = json_rpc_request(
+            self.wallet.as_deref(),
+            "gettxout",
+            &[
+                serde_json::value::to_raw_value(&outpoint.txid).unwrap(),
+                serde_json::value::to_raw_value(&outpoint.vout).unwrap(),
+            ],
+        )
+        .await?;
+        let response: jsonrpc::Response = serde_json::from_str(&response)?;
+        let result: serde_json::Value = response.result()?;
+
+        Ok(result["confirmations"].as_u64().unwrap_or(0) as u32)
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> Result<Txid> {
+        let hex = bitcoin::consensus::encode::serialize_hex(tx);
+        let response = json_rpc_request(
+            self.wallet.as_deref(),
+            "sendrawtransaction",
+            &[serde_json::value::to_raw_value(&hex).unwrap()],
+        )
+        .await?;
+        let response: jsonrpc::Response = serde_json::from_str(&response)?;
+        Ok(response.result()?)
+    }
+}
+
+/// Converts a transaction's confirmed block height into a confirmation count against the current
+/// tip, the way `gettxout`'s `confirmations` field does: the confirming block itself counts as 1.
+fn confirmations_from_heights(tip_height: i32, confirmed_height: i32) -> u32 {
+    (tip_height.saturating_sub(confirmed_height) + 1).max(0) as u32
+}
+
+/// A light-client backend modeled on the electrs/`electrum-client` wrappers BDK-based wallets
+/// use: script-hash history for confirmations, `transaction_get` for prevouts, `estimate_fee` for
+/// fee rates. `electrum_client::Client` is a blocking client, so every call is shelled out to a
+/// blocking task.
+pub struct ElectrumBackend {
+    client: std::sync::Arc<electrum_client::Client>,
+}
+
+impl ElectrumBackend {
+    pub fn new(url: &str) -> Result<Self> {
+        let client = electrum_client::Client::new(url).context("failed to connect to electrum server")?;
+        Ok(Self {
+            client: std::sync::Arc::new(client),
+        })
+    }
+}
+
+#[async_trait]
+impl ChainBackend for ElectrumBackend {
+    async fn get_prevout(&self, outpoint: OutPoint) -> Result<TxOut> {
+        let client = self.client.clone();
+        let txid = outpoint.txid;
+        let tx: Transaction = tokio::task::spawn_blocking(move || client.transaction_get(&txid))
+            .await?
+            .context("electrum transaction_get failed")?;
+
+        tx.output
+            .get(outpoint.vout as usize)
+            .cloned()
+            .context("vout out of range for fetched transaction")
+    }
+
+    async fn estimate_fee_rate(&self) -> Result<f64> {
+        let client = self.client.clone();
+        let btc_per_kvb = tokio::task::spawn_blocking(move || client.estimate_fee(6))
+            .await?
+            .context("electrum estimate_fee failed")?;
+
+        Ok(btc_per_kvb * 100_000.0) // BTC/kvB -> sat/vB
+    }
+
+    async fn get_confirmations(&self, outpoint: OutPoint) -> Result<u32> {
+        // the only way to learn the height the transaction confirmed in over the Electrum
+        // protocol is via the owning script's history.
+        let prevout = self.get_prevout(outpoint).await?;
+
+        let client = self.client.clone();
+        let script = prevout.script_pubkey.clone();
+        let history = tokio::task::spawn_blocking(move || client.script_get_history(&script))
+            .await?
+            .context("electrum script_get_history failed")?;
+
+        let entry = history
+            .into_iter()
+            .find(|entry| entry.tx_hash == outpoint.txid)
+            .context("transaction not found in its own script's history")?;
+
+        if entry.height <= 0 {
+            return Ok(0); // still in the mempool
+        }
+
+        let client = self.client.clone();
+        let tip_height = tokio::task::spawn_blocking(move || client.block_headers_subscribe())
+            .await?
+            .context("electrum block_headers_subscribe failed")?
+            .height;
+        // block heights fit comfortably in an i32 (that overflows only past height ~2.1 billion)
+        let tip_height = tip_height as i32;
+
+        Ok(confirmations_from_heights(tip_height, entry.height))
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> Result<Txid> {
+        let client = self.client.clone();
+        let tx = tx.clone();
+        tokio::task::spawn_blocking(move || client.transaction_broadcast(&tx))
+            .await?
+            .context("electrum broadcast failed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirmations_from_heights_counts_the_confirming_block() {
+        // a transaction confirmed in the tip block itself has 1 confirmation, not 0
+        assert_eq!(confirmations_from_heights(100, 100), 1);
+    }
+
+    #[test]
+    fn test_confirmations_from_heights_several_blocks_back() {
+        assert_eq!(confirmations_from_heights(106, 100), 7);
+    }
+}
@@ -1,26 +1,61 @@
 use std::str::FromStr;
 
+use anyhow::{bail, Result};
 use bitcoin::{
     absolute::LockTime,
     key::UntweakedPublicKey,
+    psbt::{Input as PsbtInput, Psbt},
+    script::Builder,
     sighash::{Prevouts, SighashCache},
+    taproot::{LeafVersion, TapLeafHash, TapNodeHash, TaprootBuilder, TaprootSpendInfo},
     transaction::Version,
     Address, Amount, OutPoint, PublicKey, ScriptBuf, Sequence, TapSighashType, TapTweakHash,
     Transaction, TxIn, TxOut, Txid, Witness,
 };
 use secp256k1::{hashes::Hash, XOnlyPublicKey};
+use thiserror::Error;
 
-use crate::constants::ZKBITCOIN_PUBKEY;
+use crate::{address_verifier::AddressVerifier, constants::ZKBITCOIN_PUBKEY};
 
-pub fn create_transaction(
+/// Why [`create_transaction`] refused to build a spend.
+#[derive(Debug, Error)]
+pub enum CreateTransactionError {
+    #[error("bob_address {0} is on the OFAC sanctions list")]
+    SanctionedAddress(Address),
+}
+
+// MuSig2 (`musig_*` functions) leans on `secp256k1-zkp`'s `musig` module rather than the plain
+// `secp256k1` crate used by the single-key `sign_transaction_schnorr`; the two crates share wire formats so
+// signatures/keys round-trip between them via their serialized bytes.
+
+/// Returns the zkBitcoin-controlled P2TR script/internal key, tweaked with `merkle_root`. Pass
+/// `None` for a plain contract; pass [`refund_leaf_merkle_root`]'s output whenever the contract
+/// UTXO being built or spent has a refund leaf, so every caller (funding, key-spend signing, and
+/// script-path refund) tweaks the same internal key the same way.
+fn zkbitcoin_script_pubkey(merkle_root: Option<TapNodeHash>) -> (ScriptBuf, UntweakedPublicKey) {
+    let secp = secp256k1::Secp256k1::default();
+    let zkbitcoin_pubkey: PublicKey = PublicKey::from_str(ZKBITCOIN_PUBKEY).unwrap();
+    let internal_key = UntweakedPublicKey::from(zkbitcoin_pubkey);
+    (ScriptBuf::new_p2tr(&secp, internal_key, merkle_root), internal_key)
+}
+
+pub async fn create_transaction(
     utxo: (Txid, u32),
     satoshi_amount: u64,
     bob_address: Address,
     fee_bitcoin_sat: u64,
     fee_zkbitcoin_sat: u64,
-) -> Transaction {
+    address_verifier: &AddressVerifier,
+) -> Result<Transaction, CreateTransactionError> {
     // TODO: should we enforce that tx.value == amount?
 
+    if address_verifier
+        .is_sanctioned(&bob_address.to_string())
+        .await
+    {
+        return Err(CreateTransactionError::SanctionedAddress(bob_address));
+    }
+
     let inputs = {
         // the first input is the smart contract we're unlocking
         let input = TxIn {
@@ -49,12 +84,10 @@ pub fn create_transaction(
         });
 
         // second output is to us
-        let secp = secp256k1::Secp256k1::default();
-        let zkbitcoin_pubkey: PublicKey = PublicKey::from_str(ZKBITCOIN_PUBKEY).unwrap();
-        let internal_key = UntweakedPublicKey::from(zkbitcoin_pubkey);
+        let (zkbitcoin_script_pubkey, _) = zkbitcoin_script_pubkey(None);
         outputs.push(TxOut {
             value: Amount::from_sat(fee_zkbitcoin_sat),
-            script_pubkey: ScriptBuf::new_p2tr(&secp, internal_key, None),
+            script_pubkey: zkbitcoin_script_pubkey,
         });
 
         outputs
@@ -66,47 +99,284 @@ pub fn create_transaction(
         input: inputs,
         output: outputs,
     };
-    tx
+    Ok(tx)
+}
+
+/// Same as [`create_transaction`], but returns a [`Psbt`] so that each committee member can
+/// inspect (and [`verify_spend_psbt`]) the exact outputs they're about to sign for, instead of
+/// trusting the coordinator's word that a raw [`Transaction`] is what was agreed on.
+pub async fn create_transaction_psbt(
+    utxo: (Txid, u32),
+    contract_utxo: TxOut,
+    satoshi_amount: u64,
+    bob_address: Address,
+    fee_bitcoin_sat: u64,
+    fee_zkbitcoin_sat: u64,
+    address_verifier: &AddressVerifier,
+) -> Result<Psbt, CreateTransactionError> {
+    let tx = create_transaction(
+        utxo,
+        satoshi_amount,
+        bob_address,
+        fee_bitcoin_sat,
+        fee_zkbitcoin_sat,
+        address_verifier,
+    )
+    .await?;
+
+    let mut psbt = Psbt::from_unsigned_tx(tx).expect("create_transaction never sets a witness");
+
+    let (_, internal_key) = zkbitcoin_script_pubkey(None);
+    psbt.inputs[0] = PsbtInput {
+        witness_utxo: Some(contract_utxo),
+        tap_internal_key: Some(internal_key),
+        ..Default::default()
+    };
+
+    Ok(psbt)
+}
+
+/// Checks that `psbt` spends exactly `expected_outpoint` and pays exactly `expected_amount`
+/// satoshis to `expected_bob_address` and exactly `expected_zkbitcoin_fee` satoshis to the
+/// zkBitcoin P2TR key, rejecting anything else. Each signer must call this before contributing a
+/// signature so that a coordinator can't trick the committee into authorizing a theft by handing
+/// out a PSBT whose outputs look right but whose input spends a different depositor's UTXO.
+pub fn verify_spend_psbt(
+    psbt: &Psbt,
+    expected_outpoint: OutPoint,
+    expected_bob_address: &Address,
+    expected_amount: u64,
+    expected_zkbitcoin_fee: u64,
+) -> Result<()> {
+    if psbt.unsigned_tx.input.len() != 1
+        || psbt.unsigned_tx.input[0].previous_output != expected_outpoint
+    {
+        bail!("psbt does not spend the expected contract outpoint {expected_outpoint}");
+    }
+
+    let (zkbitcoin_script_pubkey, _) = zkbitcoin_script_pubkey(None);
+
+    let pays_bob = psbt.unsigned_tx.output.iter().any(|out| {
+        out.script_pubkey == expected_bob_address.script_pubkey()
+            && out.value == Amount::from_sat(expected_amount)
+    });
+    if !pays_bob {
+        bail!("psbt does not pay {expected_amount} sats to the expected Bob address");
+    }
+
+    let pays_zkbitcoin = psbt.unsigned_tx.output.iter().any(|out| {
+        out.script_pubkey == zkbitcoin_script_pubkey
+            && out.value == Amount::from_sat(expected_zkbitcoin_fee)
+    });
+    if !pays_zkbitcoin {
+        bail!("psbt does not pay {expected_zkbitcoin_fee} sats to zkbitcoin's key");
+    }
+
+    if psbt.unsigned_tx.output.len() != 2 {
+        bail!("psbt has unexpected outputs beyond the Bob and zkbitcoin payouts");
+    }
+
+    Ok(())
+}
+
+/// The script-path leaf that lets the original depositor unilaterally reclaim a contract UTXO
+/// once `locktime` has passed, in case the zkBitcoin committee is offline or censors withdrawals.
+/// The committee's key-spend is the happy path, this leaf is the expiry path. There is a single
+/// timelock (`locktime`); there is no separate cancel timelock before the refund one.
+pub fn refund_script_leaf(depositor_pubkey: &XOnlyPublicKey, locktime: LockTime) -> ScriptBuf {
+    Builder::new()
+        .push_lock_time(locktime)
+        .push_opcode(bitcoin::opcodes::all::OP_CLTV)
+        .push_opcode(bitcoin::opcodes::all::OP_DROP)
+        .push_x_only_key(depositor_pubkey)
+        .push_opcode(bitcoin::opcodes::all::OP_CHECKSIG)
+        .into_script()
+}
+
+/// The taproot merkle root that commits [`refund_script_leaf`] — for a single-leaf tree, that's
+/// just the leaf's own `TapLeafHash`, which is exactly what `TaprootBuilder` computes internally
+/// in [`contract_taproot_spend_info`]. Pass this to [`zkbitcoin_script_pubkey`],
+/// [`sign_transaction_schnorr`], and [`musig_key_agg_cache`] whenever the contract UTXO being
+/// funded or spent has a refund leaf, so funding, key-spend signing, and script-path refund all
+/// tweak the zkBitcoin internal key the same way.
+pub fn refund_leaf_merkle_root(depositor_pubkey: &XOnlyPublicKey, locktime: LockTime) -> TapNodeHash {
+    let leaf = refund_script_leaf(depositor_pubkey, locktime);
+    TapNodeHash::from(TapLeafHash::from_script(&leaf, LeafVersion::TapScript))
+}
+
+/// Builds the taproot spend info for a contract UTXO: the zkBitcoin key as the key-spend path,
+/// and [`refund_script_leaf`] as the only script-path leaf. Used both when the contract UTXO is
+/// funded and when spending it, so both sides agree on the same output script.
+pub fn contract_taproot_spend_info(
+    depositor_pubkey: &XOnlyPublicKey,
+    locktime: LockTime,
+) -> TaprootSpendInfo {
+    let secp = secp256k1::Secp256k1::default();
+    let (_, internal_key) = zkbitcoin_script_pubkey(None);
+    let refund_leaf = refund_script_leaf(depositor_pubkey, locktime);
+
+    TaprootBuilder::new()
+        .add_leaf(0, refund_leaf)
+        .unwrap()
+        .finalize(&secp, internal_key)
+        .unwrap()
+}
+
+/// Spends a contract UTXO back to `refund_address` via the refund script-path leaf. Only valid
+/// once `locktime` is reached: `Transaction::lock_time` carries it, and the input's `Sequence` is
+/// set to a non-final value so nodes actually enforce the CLTV in the refund script instead of
+/// ignoring it (a final sequence disables locktime checking entirely).
+pub fn create_refund_transaction(
+    utxo: (Txid, u32),
+    satoshi_amount: u64,
+    refund_address: Address,
+    fee_bitcoin_sat: u64,
+    locktime: LockTime,
+) -> Transaction {
+    let input = TxIn {
+        previous_output: OutPoint {
+            txid: utxo.0,
+            vout: utxo.1,
+        },
+        script_sig: ScriptBuf::new(),
+        sequence: Sequence::ENABLE_LOCKTIME_NO_RBF,
+        witness: Witness::new(),
+    };
+
+    let output = TxOut {
+        value: Amount::from_sat(satoshi_amount - fee_bitcoin_sat),
+        script_pubkey: refund_address.script_pubkey(),
+    };
+
+    Transaction {
+        version: Version::TWO,
+        lock_time: locktime,
+        input: vec![input],
+        output: vec![output],
+    }
+}
+
+/// Computes the BIP341 key-spend sighash for `tx`'s first input, shared by both the single-key
+/// and the MuSig2 signing paths so they sign the exact same message.
+pub fn key_spend_sighash(tx: &Transaction, prevouts: &[TxOut]) -> bitcoin::TapSighash {
+    let mut cache = SighashCache::new(tx);
+    cache
+        .taproot_signature_hash(0, &Prevouts::All(prevouts), None, None, TapSighashType::All)
+        .unwrap()
 }
 
+/// `merkle_root` must be `None` for a plain contract, or [`refund_leaf_merkle_root`]'s output for
+/// a contract funded with a refund leaf — it must match whatever the contract UTXO was actually
+/// funded to, or this signs for the wrong taproot output key.
 pub fn sign_transaction_schnorr(
     sk: &secp256k1::SecretKey,
     tx: &Transaction,
     prevouts: &[TxOut],
+    merkle_root: Option<TapNodeHash>,
 ) -> secp256k1::schnorr::Signature {
     // key
     let secp = &secp256k1::Secp256k1::new();
     let keypair = secp256k1::Keypair::from_secret_key(secp, &sk);
     let (internal_key, _parity) = XOnlyPublicKey::from_keypair(&keypair);
-    let tweak = TapTweakHash::from_key_and_tweak(internal_key, None);
+    let tweak = TapTweakHash::from_key_and_tweak(internal_key, merkle_root);
     let tweaked_keypair = keypair.add_xonly_tweak(secp, &tweak.to_scalar()).unwrap();
 
-    // the first input is the taproot UTXO we want to spend
-    let tx_ind = 0;
+    let sighash = key_spend_sighash(tx, prevouts);
+    let msg = secp256k1::Message::from_digest(sighash.to_byte_array());
 
-    // the sighash flag is always ALL
-    let hash_ty = TapSighashType::All;
+    secp.sign_schnorr_with_aux_rand(&msg, &tweaked_keypair, &[0u8; 32])
+}
 
-    // sighash
-    let mut cache = SighashCache::new(tx);
-    let mut sig_msg = Vec::new();
-    cache
-        .taproot_encode_signing_data_to(
-            &mut sig_msg,
-            tx_ind,
-            &Prevouts::All(prevouts),
-            None,
-            None,
-            hash_ty,
-        )
-        .unwrap();
-    let sighash = cache
-        .taproot_signature_hash(tx_ind, &Prevouts::All(prevouts), None, None, hash_ty)
+/// One signer's first-round MuSig2 contribution: a public nonce to hand to the other signers,
+/// and the matching secret nonce that must be kept until [`musig_partial_sign`].
+pub struct MusigNonceRound {
+    pub pub_nonce: secp256k1_zkp::musig::MusigPubNonce,
+    pub sec_nonce: secp256k1_zkp::musig::MusigSecNonce,
+}
+
+/// Round 1: each committee member generates a fresh nonce pair over the aggregate public key and
+/// the message they're about to co-sign. `session_id` must be unique per signing attempt and
+/// never reused, or the secret key leaks.
+pub fn musig_nonce_round(
+    key_agg_cache: &secp256k1_zkp::musig::MusigKeyAggCache,
+    pubkey: secp256k1_zkp::PublicKey,
+    msg: &bitcoin::TapSighash,
+    session_id: [u8; 32],
+) -> MusigNonceRound {
+    let secp = secp256k1_zkp::Secp256k1::new();
+    let msg = secp256k1_zkp::Message::from_digest(msg.to_byte_array());
+
+    let (sec_nonce, pub_nonce) = key_agg_cache.nonce_gen(&secp, session_id, pubkey, msg, None);
+
+    MusigNonceRound {
+        pub_nonce,
+        sec_nonce,
+    }
+}
+
+/// Round 2: aggregates every signer's public nonce and opens a [`secp256k1_zkp::musig::MusigSession`]
+/// against the tweaked aggregate key, the same key-spend message every signer is signing over.
+pub fn musig_aggregate_nonces(
+    key_agg_cache: &secp256k1_zkp::musig::MusigKeyAggCache,
+    pub_nonces: &[secp256k1_zkp::musig::MusigPubNonce],
+    msg: &bitcoin::TapSighash,
+) -> secp256k1_zkp::musig::MusigSession {
+    let secp = secp256k1_zkp::Secp256k1::new();
+    let agg_nonce = secp256k1_zkp::musig::MusigAggNonce::new(&secp, pub_nonces);
+    let msg = secp256k1_zkp::Message::from_digest(msg.to_byte_array());
+
+    secp256k1_zkp::musig::MusigSession::new(&secp, key_agg_cache, agg_nonce, msg)
+}
+
+/// Builds the [`MusigKeyAggCache`](secp256k1_zkp::musig::MusigKeyAggCache) for a set of signer
+/// keys and applies the taproot tweak to the aggregate key, not to any individual signer's key,
+/// so the resulting signature validates under the same tweaked key a single-signer spend would.
+/// `merkle_root` must be `None` for a plain contract, or [`refund_leaf_merkle_root`]'s output for
+/// a contract funded with a refund leaf, matching [`sign_transaction_schnorr`]'s contract. Returns
+/// the cache alongside the tweaked aggregate x-only key, which callers need to build (or check
+/// against) the contract's P2TR output.
+pub fn musig_key_agg_cache(
+    pubkeys: &[secp256k1_zkp::PublicKey],
+    merkle_root: Option<TapNodeHash>,
+) -> (secp256k1_zkp::musig::MusigKeyAggCache, XOnlyPublicKey) {
+    let secp = secp256k1_zkp::Secp256k1::new();
+    let mut key_agg_cache = secp256k1_zkp::musig::MusigKeyAggCache::new(&secp, pubkeys);
+
+    let internal_key = key_agg_cache.agg_pk();
+    let tweak = TapTweakHash::from_key_and_tweak(
+        XOnlyPublicKey::from_slice(&internal_key.serialize()).unwrap(),
+        merkle_root,
+    );
+    let tweaked_pubkey = key_agg_cache
+        .pubkey_xonly_tweak_add(&secp, tweak.to_scalar().into())
         .unwrap();
-    let msg = secp256k1::Message::from_digest(sighash.to_byte_array());
-    let key_spend_sig = secp.sign_schnorr_with_aux_rand(&msg, &tweaked_keypair, &[0u8; 32]);
+    let (tweaked_xonly, _parity) = tweaked_pubkey.x_only_public_key();
+    let tweaked_xonly = XOnlyPublicKey::from_slice(&tweaked_xonly.serialize()).unwrap();
+
+    (key_agg_cache, tweaked_xonly)
+}
 
-    key_spend_sig
+/// Each signer's contribution in round 2, produced from their own secret nonce and keypair.
+pub fn musig_partial_sign(
+    session: &secp256k1_zkp::musig::MusigSession,
+    sec_nonce: secp256k1_zkp::musig::MusigSecNonce,
+    keypair: &secp256k1_zkp::Keypair,
+    key_agg_cache: &secp256k1_zkp::musig::MusigKeyAggCache,
+) -> secp256k1_zkp::musig::MusigPartialSignature {
+    session
+        .partial_sign(sec_nonce, keypair, key_agg_cache)
+        .expect("own nonce/keypair must be valid for this session")
+}
+
+/// Combines every signer's partial signature into the final 64-byte schnorr signature, a
+/// drop-in replacement for [`sign_transaction_schnorr`]'s output: it validates under the
+/// aggregated (tweaked) internal key with the same `TapSighashType::All` witness layout.
+pub fn musig_aggregate(
+    session: &secp256k1_zkp::musig::MusigSession,
+    partial_sigs: &[secp256k1_zkp::musig::MusigPartialSignature],
+) -> secp256k1::schnorr::Signature {
+    let agg_sig = session.partial_sig_agg(partial_sigs);
+    secp256k1::schnorr::Signature::from_slice(agg_sig.as_byte_array()).unwrap()
 }
 
 #[cfg(test)]
@@ -165,14 +435,187 @@ mod tests {
         (tx, prevouts)
     }
 
+    #[tokio::test]
+    async fn test_create_transaction_psbt_verifies() {
+        let txid = Txid::all_zeros();
+        let vout = 0;
+        let satoshi_amount = 1000;
+        let fee_bitcoin_sat = 100;
+        let fee_zkbitcoin_sat = 50;
+
+        let bob_address = Address::from_str(ZKBITCOIN_ADDRESS)
+            .unwrap()
+            .require_network(Network::Testnet)
+            .unwrap();
+
+        let contract_utxo = TxOut {
+            value: Amount::from_sat(satoshi_amount),
+            script_pubkey: ScriptBuf::new(),
+        };
+
+        let address_verifier = AddressVerifier::new(
+            std::env::temp_dir().join("test_create_transaction_psbt_verifies.json"),
+            vec![AddressVerifier::BITCOIN_FEATURE_TYPE_ID.to_string()],
+        )
+        .await;
+
+        let psbt = create_transaction_psbt(
+            (txid, vout),
+            contract_utxo,
+            satoshi_amount,
+            bob_address.clone(),
+            fee_bitcoin_sat,
+            fee_zkbitcoin_sat,
+            &address_verifier,
+        )
+        .await
+        .unwrap();
+
+        let expected_outpoint = OutPoint { txid, vout };
+
+        verify_spend_psbt(
+            &psbt,
+            expected_outpoint,
+            &bob_address,
+            satoshi_amount - fee_bitcoin_sat - fee_zkbitcoin_sat,
+            fee_zkbitcoin_sat,
+        )
+        .unwrap();
+
+        // a coordinator lying about the amount owed to Bob must be rejected
+        assert!(verify_spend_psbt(
+            &psbt,
+            expected_outpoint,
+            &bob_address,
+            satoshi_amount,
+            fee_zkbitcoin_sat
+        )
+        .is_err());
+
+        // a coordinator swapping in a different depositor's contract utxo must be rejected even
+        // though the outputs still look correct
+        let wrong_outpoint = OutPoint {
+            txid: Txid::all_zeros(),
+            vout: vout + 1,
+        };
+        assert!(verify_spend_psbt(
+            &psbt,
+            wrong_outpoint,
+            &bob_address,
+            satoshi_amount - fee_bitcoin_sat - fee_zkbitcoin_sat,
+            fee_zkbitcoin_sat
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_transaction_rejects_sanctioned_bob_address() {
+        let bob_address = Address::from_str(ZKBITCOIN_ADDRESS)
+            .unwrap()
+            .require_network(Network::Testnet)
+            .unwrap();
+
+        // seed the on-disk cache `AddressVerifier::new` loads from, so this test doesn't depend
+        // on reaching the real OFAC server
+        let cache_path =
+            std::env::temp_dir().join("test_create_transaction_rejects_sanctioned_bob_address.json");
+        let disk_cache = serde_json::json!({
+            "last_update": 0,
+            "sanctioned_addresses": { bob_address.to_string(): true },
+        });
+        std::fs::write(&cache_path, serde_json::to_vec(&disk_cache).unwrap()).unwrap();
+
+        let address_verifier = AddressVerifier::new(
+            cache_path,
+            vec![AddressVerifier::BITCOIN_FEATURE_TYPE_ID.to_string()],
+        )
+        .await;
+
+        let result = create_transaction(
+            (Txid::all_zeros(), 0),
+            1000,
+            bob_address,
+            100,
+            50,
+            &address_verifier,
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(CreateTransactionError::SanctionedAddress(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_refund_transaction_enforces_locktime() {
+        let txid = Txid::all_zeros();
+        let locktime = LockTime::from_height(800_000).unwrap();
+
+        let refund_address = Address::from_str(ZKBITCOIN_ADDRESS)
+            .unwrap()
+            .require_network(Network::Testnet)
+            .unwrap();
+
+        let tx = create_refund_transaction(
+            (txid, 0),
+            1000,
+            refund_address,
+            100,
+            locktime,
+        );
+
+        assert_eq!(tx.lock_time, locktime);
+        assert!(!tx.input[0].sequence.is_final());
+    }
+
     #[test]
     fn test_sign_tx() {
         let sk = secp256k1::SecretKey::new(&mut rand::thread_rng());
         let (tx, prevouts) = create_dummy_tx();
-        let sig = sign_transaction_schnorr(&sk, &tx, &prevouts);
+        let sig = sign_transaction_schnorr(&sk, &tx, &prevouts, None);
         println!("{sig:?}");
     }
 
+    #[test]
+    fn test_musig2_two_signers_produces_valid_signature() {
+        let secp = secp256k1_zkp::Secp256k1::new();
+        let (tx, prevouts) = create_dummy_tx();
+        let sighash = key_spend_sighash(&tx, &prevouts);
+
+        let keypair_1 = secp256k1_zkp::Keypair::new(&secp, &mut rand::thread_rng());
+        let keypair_2 = secp256k1_zkp::Keypair::new(&secp, &mut rand::thread_rng());
+        let pubkey_1 = keypair_1.public_key();
+        let pubkey_2 = keypair_2.public_key();
+
+        let (key_agg_cache, tweaked_agg_key) = musig_key_agg_cache(&[pubkey_1, pubkey_2], None);
+
+        let nonce_round_1 =
+            musig_nonce_round(&key_agg_cache, pubkey_1, &sighash, [1u8; 32]);
+        let nonce_round_2 =
+            musig_nonce_round(&key_agg_cache, pubkey_2, &sighash, [2u8; 32]);
+
+        let session = musig_aggregate_nonces(
+            &key_agg_cache,
+            &[nonce_round_1.pub_nonce, nonce_round_2.pub_nonce],
+            &sighash,
+        );
+
+        let partial_sig_1 =
+            musig_partial_sign(&session, nonce_round_1.sec_nonce, &keypair_1, &key_agg_cache);
+        let partial_sig_2 =
+            musig_partial_sign(&session, nonce_round_2.sec_nonce, &keypair_2, &key_agg_cache);
+
+        let signature = musig_aggregate(&session, &[partial_sig_1, partial_sig_2]);
+
+        // the final signature must validate under the taproot-tweaked aggregate key, the same key
+        // a single-signer key-spend for this contract would use.
+        let msg = secp256k1::Message::from_digest(sighash.to_byte_array());
+        secp256k1::Secp256k1::verification_only()
+            .verify_schnorr(&signature, &msg, &tweaked_agg_key)
+            .expect("aggregated musig2 signature must verify under the tweaked aggregate key");
+    }
+
     #[tokio::test]
     async fn test_real_tx() {
         // txid from https://blockstream.info/testnet/tx/0a38352d1ba4efdc785bc895abdb3f3185624100509d45aa2663b27a2fc094ea?expand
@@ -189,13 +632,23 @@ mod tests {
 
         let fee_bitcoin_sat = 800;
         let fee_zkbitcoin_sat = 200;
+
+        let address_verifier = AddressVerifier::new(
+            std::env::temp_dir().join("test_real_tx.json"),
+            vec![AddressVerifier::BITCOIN_FEATURE_TYPE_ID.to_string()],
+        )
+        .await;
+
         let mut tx = create_transaction(
             (txid, vout),
             satoshi_amount,
             bob_address,
             fee_bitcoin_sat,
             fee_zkbitcoin_sat,
-        );
+            &address_verifier,
+        )
+        .await
+        .unwrap();
 
         // prevouts
         let prevouts = vec![TxOut {
@@ -208,7 +661,7 @@ mod tests {
 
         // sign
         let sk = secp256k1::SecretKey::new(&mut rand::thread_rng());
-        let sig = sign_transaction_schnorr(&sk, &tx, &prevouts);
+        let sig = sign_transaction_schnorr(&sk, &tx, &prevouts, None);
 
         // place signature in witness
         let hash_ty = TapSighashType::All;
@@ -0,0 +1,253 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use bitcoin::{Amount, OutPoint, ScriptBuf};
+use log::info;
+use serde::Deserialize;
+use tokio::{
+    sync::RwLock,
+    time::{sleep, Duration, Instant},
+};
+
+use crate::json_rpc_stuff::json_rpc_request;
+
+/// How many confirmations we consider deep enough to be safe from reorgs.
+const SAFETY_MARGIN: u32 = 6;
+
+/// How often we re-scan for new blocks while waiting on a UTXO.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Average time between blocks, used to size [`wait_for_confirmations`]'s timeout. This is a
+/// wall-clock budget, not a poll count: reaching `SAFETY_MARGIN` confirmations takes on the order
+/// of an hour on mainnet, far longer than the 10s [`POLL_INTERVAL`] between scans.
+const AVG_BLOCK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Extra slack on top of the expected `min_confs.max(SAFETY_MARGIN) * AVG_BLOCK_INTERVAL`, for blocks that come in
+/// slower than average.
+const TIMEOUT_SLACK: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedUtxo {
+    pub outpoint: OutPoint,
+    pub value: Amount,
+    pub confirmations: u32,
+}
+
+#[derive(Deserialize)]
+struct ScanTxOutSetUnspent {
+    txid: bitcoin::Txid,
+    vout: u32,
+    #[serde(rename = "scriptPubKey")]
+    script_pubkey: String,
+    amount: f64,
+    height: u64,
+}
+
+#[derive(Deserialize)]
+struct ScanTxOutSetResult {
+    success: bool,
+    height: Option<u64>,
+    unspents: Vec<ScanTxOutSetUnspent>,
+}
+
+/// Watches a set of scripts for funding UTXOs and keeps a `script_pubkey -> UTXO` cache of the
+/// latest scan's results between polls. Every poll re-scans the whole UTXO set; blocks already
+/// seen are not skipped.
+pub struct UtxoWatcher {
+    watched_scripts: Arc<RwLock<Vec<ScriptBuf>>>,
+    cache: Arc<RwLock<HashMap<ScriptBuf, WatchedUtxo>>>,
+}
+
+impl UtxoWatcher {
+    pub fn new() -> Self {
+        Self {
+            watched_scripts: Arc::new(RwLock::new(Vec::new())),
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Registers a script to be scanned for UTXOs on subsequent polls.
+    pub async fn watch(&self, script: ScriptBuf) {
+        self.watched_scripts.write().await.push(script);
+    }
+
+    /// Merges a fresh `scantxoutset` result into `cache`, in place. A script that was previously
+    /// cached but no longer appears in `unspents` had its UTXO spent (or reorged away) and is
+    /// dropped from the cache, rather than left behind with a stale confirmation count.
+    fn merge_scan_result(
+        cache: &mut HashMap<ScriptBuf, WatchedUtxo>,
+        tip_height: u64,
+        unspents: Vec<ScanTxOutSetUnspent>,
+    ) -> Result<()> {
+        let mut fresh = HashMap::with_capacity(unspents.len());
+        for unspent in unspents {
+            let script = ScriptBuf::from_hex(&unspent.script_pubkey)?;
+            let confirmations = (tip_height.saturating_sub(unspent.height) + 1) as u32;
+            fresh.insert(
+                script,
+                WatchedUtxo {
+                    outpoint: OutPoint {
+                        txid: unspent.txid,
+                        vout: unspent.vout,
+                    },
+                    value: Amount::from_btc(unspent.amount)?,
+                    confirmations,
+                },
+            );
+        }
+
+        cache.retain(|script, _| fresh.contains_key(script));
+        cache.extend(fresh);
+
+        Ok(())
+    }
+
+    /// Re-scans the chain for UTXOs paying the watched scripts, merging fresh results into the
+    /// cache. Each call runs a full `scantxoutset` over the current UTXO set for every watched
+    /// script.
+    async fn scan(&self) -> Result<()> {
+        let scripts = self.watched_scripts.read().await.clone();
+        let descriptors: Vec<_> = scripts
+            .iter()
+            .map(|script| serde_json::json!({ "desc": format!("raw({})", script.to_hex_string()) }))
+            .collect();
+
+        let response = json_rpc_request(
+            None,
+            "scantxoutset",
+            &[
+                serde_json::value::to_raw_value(&"start").unwrap(),
+                serde_json::value::to_raw_value(&descriptors).unwrap(),
+            ],
+        )
+        .await?;
+        let response: jsonrpc::Response = serde_json::from_str(&response)?;
+        let result: ScanTxOutSetResult = response.result()?;
+
+        if !result.success {
+            bail!("scantxoutset did not complete");
+        }
+        let tip_height = result.height.context("scantxoutset returned no tip height")?;
+
+        let mut cache = self.cache.write().await;
+        Self::merge_scan_result(&mut cache, tip_height, result.unspents)
+    }
+
+    /// Waits until `outpoint` has at least `min_confs` confirmations, polling the chain every
+    /// [`POLL_INTERVAL`]. The timeout is a wall-clock budget sized to how long
+    /// `min_confs.max(SAFETY_MARGIN)` blocks actually take to mine, not the number of polls. Errors if the UTXO disappears after having
+    /// been seen (spent or reorged out) or if `outpoint` never shows up before the deadline.
+    pub async fn wait_for_confirmations(
+        &self,
+        outpoint: OutPoint,
+        min_confs: u32,
+    ) -> Result<WatchedUtxo> {
+        let deadline =
+            Instant::now() + AVG_BLOCK_INTERVAL * min_confs.max(SAFETY_MARGIN) + TIMEOUT_SLACK;
+        let mut ever_seen = false;
+
+        loop {
+            self.scan().await?;
+
+            let cache = self.cache.read().await;
+            let utxo = cache.values().find(|utxo| utxo.outpoint == outpoint).cloned();
+            drop(cache);
+
+            match utxo {
+                Some(utxo) if utxo.confirmations >= min_confs => {
+                    info!(
+                        "utxo {:?} reached {} confirmations",
+                        utxo.outpoint, utxo.confirmations
+                    );
+                    return Ok(utxo);
+                }
+                Some(utxo) => {
+                    ever_seen = true;
+                    info!(
+                        "utxo {:?} has {}/{} confirmations, waiting",
+                        utxo.outpoint, utxo.confirmations, min_confs
+                    );
+                }
+                None if ever_seen => {
+                    bail!("utxo {outpoint:?} disappeared from the chain (spent or reorged)");
+                }
+                None => {}
+            }
+
+            if Instant::now() >= deadline {
+                bail!("utxo {outpoint:?} did not reach {min_confs} confirmations in time");
+            }
+
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+
+    fn unspent(txid: bitcoin::Txid, vout: u32, script: &ScriptBuf, height: u64) -> ScanTxOutSetUnspent {
+        ScanTxOutSetUnspent {
+            txid,
+            vout,
+            script_pubkey: script.to_hex_string(),
+            amount: 0.00001,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_merge_scan_result_drops_spent_utxo() {
+        let script = ScriptBuf::new();
+        let txid = bitcoin::Txid::all_zeros();
+
+        let mut cache = HashMap::new();
+        UtxoWatcher::merge_scan_result(
+            &mut cache,
+            100,
+            vec![unspent(txid, 0, &script, 95)],
+        )
+        .unwrap();
+        assert!(cache.contains_key(&script));
+        assert_eq!(cache[&script].confirmations, 6);
+
+        // the script no longer appears in the next scan: its utxo was spent (or reorged away)
+        UtxoWatcher::merge_scan_result(&mut cache, 101, vec![]).unwrap();
+        assert!(cache.is_empty(), "spent utxo must not linger in the cache");
+    }
+
+    #[test]
+    fn test_merge_scan_result_reuse_does_not_hide_spend() {
+        let script = ScriptBuf::new();
+        let original_txid = bitcoin::Txid::all_zeros();
+        let new_txid = bitcoin::Txid::from_str(
+            "1111111111111111111111111111111111111111111111111111111111111111",
+        )
+        .unwrap();
+
+        let mut cache = HashMap::new();
+        UtxoWatcher::merge_scan_result(
+            &mut cache,
+            100,
+            vec![unspent(original_txid, 0, &script, 95)],
+        )
+        .unwrap();
+
+        // the same script pays again with a different outpoint
+        UtxoWatcher::merge_scan_result(
+            &mut cache,
+            102,
+            vec![unspent(new_txid, 0, &script, 102)],
+        )
+        .unwrap();
+
+        // the cache now tracks the new outpoint, not the original one
+        assert_eq!(cache[&script].outpoint.txid, new_txid);
+        assert_ne!(cache[&script].outpoint.txid, original_txid);
+    }
+}